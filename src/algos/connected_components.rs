@@ -0,0 +1,35 @@
+use std::collections::HashSet;
+
+use crate::{Graph, NodeRef, Slot};
+
+impl<N, E> Graph<N, E> {
+    /// Partitions the graph into its connected components by growing a BFS
+    /// tree from every node that hasn't already been swept up by an earlier
+    /// one. Edges are followed in their stored direction only, so with
+    /// directed edges a component holds the not-yet-claimed nodes reachable
+    /// from its first node.
+    pub fn connected_components(&self) -> Vec<Vec<NodeRef>> {
+        let mut seen = HashSet::new();
+        let mut components = Vec::new();
+
+        for (idx, entry) in self.nodes.iter().enumerate() {
+            if !matches!(&entry.slot, Slot::Occupied(_)) {
+                continue;
+            }
+
+            let node = NodeRef {
+                idx,
+                generation: entry.generation,
+            };
+            if seen.contains(&node) {
+                continue;
+            }
+
+            let component: Vec<NodeRef> = self.bfs(node).filter(|n| !seen.contains(n)).collect();
+            seen.extend(component.iter().copied());
+            components.push(component);
+        }
+
+        components
+    }
+}
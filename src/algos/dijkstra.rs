@@ -0,0 +1,35 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::{Graph, NodeRef};
+
+impl<N> Graph<N, i32> {
+    /// Computes the shortest-path distance from `from` to every node, using
+    /// Dijkstra's algorithm with a binary heap keyed on path distance.
+    /// `None` for nodes unreachable from `from`.
+    pub fn dijkstra(&self, from: NodeRef) -> Vec<Option<i32>> {
+        let mut dist = vec![None; self.nodes.len()];
+        dist[from.idx] = Some(0);
+
+        let mut queue = BinaryHeap::new();
+        queue.push(Reverse((0, from)));
+
+        while let Some(Reverse((d, node))) = queue.pop() {
+            if dist[node.idx].is_some_and(|best| d > best) {
+                continue;
+            }
+
+            for adj in &self.adjacency[node.idx] {
+                let Some(&weight) = self.get_edge(adj.edge) else {
+                    continue;
+                };
+                let next = d + weight;
+                if dist[adj.node.idx].is_none_or(|best| next < best) {
+                    dist[adj.node.idx] = Some(next);
+                    queue.push(Reverse((next, adj.node)));
+                }
+            }
+        }
+
+        dist
+    }
+}
@@ -0,0 +1,9 @@
+//! Reusable graph analyses built on top of the adjacency structure, kept
+//! separate from the core `Graph` definition so new algorithms can be added
+//! without growing `lib.rs` further.
+
+mod connected_components;
+mod dijkstra;
+mod topological_sort;
+
+pub use topological_sort::CycleError;
@@ -0,0 +1,65 @@
+use std::{collections::VecDeque, fmt};
+
+use crate::{Graph, NodeRef, Slot};
+
+/// Returned by [`Graph::topological_sort`] when the graph contains a cycle,
+/// which has no valid topological order.
+#[derive(Debug)]
+pub struct CycleError;
+
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "graph contains a cycle, so no topological order exists")
+    }
+}
+
+impl std::error::Error for CycleError {}
+
+impl<N, E> Graph<N, E> {
+    /// Computes a topological order of the nodes using Kahn's algorithm,
+    /// repeatedly peeling off nodes whose in-degree has dropped to zero.
+    pub fn topological_sort(&self) -> Result<Vec<NodeRef>, CycleError> {
+        let mut in_degree = vec![0usize; self.nodes.len()];
+        for entry in &self.edges {
+            if let Slot::Occupied(data) = &entry.slot {
+                in_degree[data.to.idx] += 1;
+            }
+        }
+
+        let mut queue: VecDeque<NodeRef> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, entry)| match &entry.slot {
+                Slot::Occupied(_) if in_degree[idx] == 0 => Some(NodeRef {
+                    idx,
+                    generation: entry.generation,
+                }),
+                _ => None,
+            })
+            .collect();
+
+        let live_count = self
+            .nodes
+            .iter()
+            .filter(|entry| matches!(&entry.slot, Slot::Occupied(_)))
+            .count();
+
+        let mut order = Vec::with_capacity(live_count);
+        while let Some(node) = queue.pop_front() {
+            order.push(node);
+            for adj in &self.adjacency[node.idx] {
+                in_degree[adj.node.idx] -= 1;
+                if in_degree[adj.node.idx] == 0 {
+                    queue.push_back(adj.node);
+                }
+            }
+        }
+
+        if order.len() == live_count {
+            Ok(order)
+        } else {
+            Err(CycleError)
+        }
+    }
+}
@@ -0,0 +1,305 @@
+//! An undoable editing layer on top of [`Graph`], modeled on the classic
+//! command pattern: each [`Command`] knows how to apply itself and how to
+//! produce its own inverse, so a [`CommandHistory`] can move back and forth
+//! through a sequence of edits without knowing what any of them actually do.
+
+use std::cell::{Cell, RefCell};
+
+use crate::{EdgeRef, Graph, NodeRef};
+
+/// A reversible mutation of a `Graph<N, E>`.
+///
+/// `undo` is called while the command's effect is still applied to `graph`;
+/// it inspects that state to build the command that would reverse it, but
+/// does not itself mutate anything.
+pub trait Command<N, E> {
+    fn apply(&self, graph: &mut Graph<N, E>);
+    fn undo(&self, graph: &Graph<N, E>) -> Box<dyn Command<N, E>>;
+}
+
+/// Adds a node, recording the handle it was assigned so the addition can be
+/// undone.
+pub struct AddNode<N> {
+    value: RefCell<Option<N>>,
+    created: Cell<Option<NodeRef>>,
+}
+
+impl<N> AddNode<N> {
+    pub fn new(value: N) -> Self {
+        Self {
+            value: RefCell::new(Some(value)),
+            created: Cell::new(None),
+        }
+    }
+}
+
+impl<N: 'static, E: 'static> Command<N, E> for AddNode<N> {
+    fn apply(&self, graph: &mut Graph<N, E>) {
+        let value = self
+            .value
+            .borrow_mut()
+            .take()
+            .expect("AddNode applied more than once");
+        self.created.set(Some(graph.node(value)));
+    }
+
+    fn undo(&self, _graph: &Graph<N, E>) -> Box<dyn Command<N, E>> {
+        let node = self.created.get().expect("AddNode::undo before apply");
+        Box::new(RemoveNode::new(node))
+    }
+}
+
+/// Removes a node, recording its value so the removal can be undone.
+/// Assumes any incident edges have already been removed by their own
+/// commands; removing a node that still has edges cascades their deletion
+/// without recording separate undo entries for each.
+pub struct RemoveNode<N> {
+    node: NodeRef,
+    removed: RefCell<Option<N>>,
+}
+
+impl<N> RemoveNode<N> {
+    pub fn new(node: NodeRef) -> Self {
+        Self {
+            node,
+            removed: RefCell::new(None),
+        }
+    }
+}
+
+impl<N: 'static, E: 'static> Command<N, E> for RemoveNode<N> {
+    fn apply(&self, graph: &mut Graph<N, E>) {
+        let value = graph
+            .remove_node(self.node)
+            .expect("RemoveNode applied to a dead node");
+        *self.removed.borrow_mut() = Some(value);
+    }
+
+    fn undo(&self, _graph: &Graph<N, E>) -> Box<dyn Command<N, E>> {
+        let value = self
+            .removed
+            .borrow_mut()
+            .take()
+            .expect("RemoveNode::undo before apply");
+        Box::new(AddNode::new(value))
+    }
+}
+
+/// Adds a directed edge, recording the handle it was assigned so the
+/// addition can be undone.
+pub struct AddDirectedEdge<E> {
+    from: NodeRef,
+    to: NodeRef,
+    value: RefCell<Option<E>>,
+    created: Cell<Option<EdgeRef>>,
+}
+
+impl<E> AddDirectedEdge<E> {
+    pub fn new(from: NodeRef, to: NodeRef, value: E) -> Self {
+        Self {
+            from,
+            to,
+            value: RefCell::new(Some(value)),
+            created: Cell::new(None),
+        }
+    }
+}
+
+impl<N: 'static, E: 'static> Command<N, E> for AddDirectedEdge<E> {
+    fn apply(&self, graph: &mut Graph<N, E>) {
+        let value = self
+            .value
+            .borrow_mut()
+            .take()
+            .expect("AddDirectedEdge applied more than once");
+        self.created.set(Some(graph.directed_edge(self.from, self.to, value)));
+    }
+
+    fn undo(&self, graph: &Graph<N, E>) -> Box<dyn Command<N, E>> {
+        let edge = self.created.get().expect("AddDirectedEdge::undo before apply");
+        Box::new(RemoveEdge::new(graph, edge))
+    }
+}
+
+/// Removes an edge, recording its endpoints and value so the removal can be
+/// undone as a plain directed edge.
+pub struct RemoveEdge<E> {
+    edge: EdgeRef,
+    from: NodeRef,
+    to: NodeRef,
+    removed: RefCell<Option<E>>,
+}
+
+impl<E> RemoveEdge<E> {
+    pub fn new<N>(graph: &Graph<N, E>, edge: EdgeRef) -> Self {
+        let (from, to) = graph
+            .edge_endpoints(edge)
+            .expect("RemoveEdge::new given a dead edge");
+        Self {
+            edge,
+            from,
+            to,
+            removed: RefCell::new(None),
+        }
+    }
+}
+
+impl<N: 'static, E: 'static> Command<N, E> for RemoveEdge<E> {
+    fn apply(&self, graph: &mut Graph<N, E>) {
+        let value = graph
+            .remove_edge(self.edge)
+            .expect("RemoveEdge applied to a dead edge");
+        *self.removed.borrow_mut() = Some(value);
+    }
+
+    fn undo(&self, _graph: &Graph<N, E>) -> Box<dyn Command<N, E>> {
+        let value = self
+            .removed
+            .borrow_mut()
+            .take()
+            .expect("RemoveEdge::undo before apply");
+        Box::new(AddDirectedEdge::new(self.from, self.to, value))
+    }
+}
+
+/// Adds an undirected edge (a paired pair of directed edges, see
+/// [`Graph::undirected_edge`]), recording the handles it was assigned so the
+/// addition can be undone as a unit.
+pub struct AddUndirectedEdge<E> {
+    a: NodeRef,
+    b: NodeRef,
+    value: RefCell<Option<E>>,
+    created: Cell<Option<[EdgeRef; 2]>>,
+}
+
+impl<E> AddUndirectedEdge<E> {
+    pub fn new(a: NodeRef, b: NodeRef, value: E) -> Self {
+        Self {
+            a,
+            b,
+            value: RefCell::new(Some(value)),
+            created: Cell::new(None),
+        }
+    }
+}
+
+impl<N: 'static, E: Clone + 'static> Command<N, E> for AddUndirectedEdge<E> {
+    fn apply(&self, graph: &mut Graph<N, E>) {
+        let value = self
+            .value
+            .borrow_mut()
+            .take()
+            .expect("AddUndirectedEdge applied more than once");
+        self.created.set(Some(graph.undirected_edge(self.a, self.b, value)));
+    }
+
+    fn undo(&self, _graph: &Graph<N, E>) -> Box<dyn Command<N, E>> {
+        let edges = self
+            .created
+            .get()
+            .expect("AddUndirectedEdge::undo before apply");
+        Box::new(RemoveUndirectedEdge::new(edges, self.a, self.b))
+    }
+}
+
+/// Removes both edges of an undirected pair, recording their value so the
+/// removal can be undone as a single [`AddUndirectedEdge`] (preserving the
+/// pairing between the two directions).
+pub struct RemoveUndirectedEdge<E> {
+    edges: [EdgeRef; 2],
+    a: NodeRef,
+    b: NodeRef,
+    removed: RefCell<Option<E>>,
+}
+
+impl<E> RemoveUndirectedEdge<E> {
+    pub fn new(edges: [EdgeRef; 2], a: NodeRef, b: NodeRef) -> Self {
+        Self {
+            edges,
+            a,
+            b,
+            removed: RefCell::new(None),
+        }
+    }
+}
+
+impl<N: 'static, E: Clone + 'static> Command<N, E> for RemoveUndirectedEdge<E> {
+    fn apply(&self, graph: &mut Graph<N, E>) {
+        graph.remove_edge(self.edges[1]);
+        let value = graph
+            .remove_edge(self.edges[0])
+            .expect("RemoveUndirectedEdge applied to a dead edge");
+        *self.removed.borrow_mut() = Some(value);
+    }
+
+    fn undo(&self, _graph: &Graph<N, E>) -> Box<dyn Command<N, E>> {
+        let value = self
+            .removed
+            .borrow_mut()
+            .take()
+            .expect("RemoveUndirectedEdge::undo before apply");
+        Box::new(AddUndirectedEdge::new(self.a, self.b, value))
+    }
+}
+
+/// Records a sequence of applied commands and a cursor into it, so edits can
+/// be undone and redone. Each slot holds whichever of a command/its inverse
+/// is currently "applied"; undoing and redoing both work by asking that
+/// slot's occupant for its inverse and swapping it in, which is why
+/// `Command::undo` is enough to support both directions.
+pub struct CommandHistory<N, E> {
+    entries: Vec<Box<dyn Command<N, E>>>,
+    cursor: usize,
+}
+
+impl<N, E> Default for CommandHistory<N, E> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            cursor: 0,
+        }
+    }
+}
+
+impl<N, E> CommandHistory<N, E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies `command` to `graph` and records it, discarding any
+    /// previously undone commands still sitting after the cursor.
+    pub fn apply(&mut self, graph: &mut Graph<N, E>, command: Box<dyn Command<N, E>>) {
+        command.apply(graph);
+        self.entries.truncate(self.cursor);
+        self.entries.push(command);
+        self.cursor = self.entries.len();
+    }
+
+    /// Undoes the most recently applied command. Returns `false` if there
+    /// was nothing to undo.
+    pub fn undo(&mut self, graph: &mut Graph<N, E>) -> bool {
+        if self.cursor == 0 {
+            return false;
+        }
+
+        self.cursor -= 1;
+        let inverse = self.entries[self.cursor].undo(graph);
+        inverse.apply(graph);
+        self.entries[self.cursor] = inverse;
+        true
+    }
+
+    /// Re-applies the most recently undone command. Returns `false` if
+    /// there was nothing to redo.
+    pub fn redo(&mut self, graph: &mut Graph<N, E>) -> bool {
+        if self.cursor == self.entries.len() {
+            return false;
+        }
+
+        let forward = self.entries[self.cursor].undo(graph);
+        forward.apply(graph);
+        self.entries[self.cursor] = forward;
+        self.cursor += 1;
+        true
+    }
+}
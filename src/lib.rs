@@ -3,14 +3,19 @@ use std::{
     ops::{Index, IndexMut},
 };
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub mod algos;
+pub mod command;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct NodeRef {
     idx: usize,
+    generation: u32,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub struct EdgeRef {
     idx: usize,
+    generation: u32,
 }
 
 struct AdjEntry {
@@ -18,16 +23,35 @@ struct AdjEntry {
     edge: EdgeRef,
 }
 
-struct IncEntry {
+/// The payload of an edge slot: the endpoints the edge was created with, its
+/// weight, and (for edges added via `undirected_edge`) the edge forming the
+/// other direction of the pair.
+struct EdgeData<E> {
     from: NodeRef,
     to: NodeRef,
+    reverse: Option<EdgeRef>,
+    value: E,
+}
+
+/// A generational storage slot: either occupied by a live value, or free and
+/// linking to the next free slot. Every slot remembers its own generation so
+/// that a handle into a reused slot can be told apart from a stale one.
+enum Slot<T> {
+    Occupied(T),
+    Free { next_free: Option<usize> },
+}
+
+struct Entry<T> {
+    generation: u32,
+    slot: Slot<T>,
 }
 
 pub struct Graph<N, E> {
-    nodes: Vec<N>,
-    edges: Vec<E>,
+    nodes: Vec<Entry<N>>,
+    edges: Vec<Entry<EdgeData<E>>>,
     adjacency: Vec<Vec<AdjEntry>>,
-    incidence: Vec<IncEntry>,
+    free_nodes: Option<usize>,
+    free_edges: Option<usize>,
 }
 
 impl<N, E> Default for Graph<N, E> {
@@ -36,11 +60,75 @@ impl<N, E> Default for Graph<N, E> {
             nodes: Default::default(),
             edges: Default::default(),
             adjacency: Default::default(),
-            incidence: Default::default(),
+            free_nodes: None,
+            free_edges: None,
         }
     }
 }
 
+/// Inserts `value` into `entries`, reusing a free slot from `free_head` if
+/// one is available, and returns its index and generation.
+fn insert_entry<T>(entries: &mut Vec<Entry<T>>, free_head: &mut Option<usize>, value: T) -> (usize, u32) {
+    if let Some(idx) = *free_head {
+        let entry = &mut entries[idx];
+        *free_head = match entry.slot {
+            Slot::Free { next_free } => next_free,
+            Slot::Occupied(_) => unreachable!("free list pointed at an occupied slot"),
+        };
+        entry.generation += 1;
+        entry.slot = Slot::Occupied(value);
+        (idx, entry.generation)
+    } else {
+        let idx = entries.len();
+        entries.push(Entry {
+            generation: 0,
+            slot: Slot::Occupied(value),
+        });
+        (idx, 0)
+    }
+}
+
+/// Removes the value at `idx` from `entries` if its generation matches,
+/// threading the freed slot onto `free_head`.
+fn remove_entry<T>(
+    entries: &mut [Entry<T>],
+    free_head: &mut Option<usize>,
+    idx: usize,
+    generation: u32,
+) -> Option<T> {
+    let entry = entries.get_mut(idx)?;
+    if entry.generation != generation || !matches!(entry.slot, Slot::Occupied(_)) {
+        return None;
+    }
+
+    let value = match std::mem::replace(&mut entry.slot, Slot::Free { next_free: *free_head }) {
+        Slot::Occupied(value) => value,
+        Slot::Free { .. } => return None,
+    };
+    *free_head = Some(idx);
+    Some(value)
+}
+
+fn get_entry<T>(entries: &[Entry<T>], idx: usize, generation: u32) -> Option<&T> {
+    match entries.get(idx) {
+        Some(entry) if entry.generation == generation => match &entry.slot {
+            Slot::Occupied(value) => Some(value),
+            Slot::Free { .. } => None,
+        },
+        _ => None,
+    }
+}
+
+fn get_entry_mut<T>(entries: &mut [Entry<T>], idx: usize, generation: u32) -> Option<&mut T> {
+    match entries.get_mut(idx) {
+        Some(entry) if entry.generation == generation => match &mut entry.slot {
+            Slot::Occupied(value) => Some(value),
+            Slot::Free { .. } => None,
+        },
+        _ => None,
+    }
+}
+
 impl<N, E> Graph<N, E> {
     /// Constructs an empty graph
     pub fn empty() -> Self {
@@ -48,29 +136,95 @@ impl<N, E> Graph<N, E> {
     }
 
     pub fn node(&mut self, n: N) -> NodeRef {
-        let r = NodeRef {
-            idx: self.nodes.len(),
-        };
-        self.nodes.push(n);
-        self.adjacency.push(Vec::new());
-        r
+        let (idx, generation) = insert_entry(&mut self.nodes, &mut self.free_nodes, n);
+        if idx == self.adjacency.len() {
+            self.adjacency.push(Vec::new());
+        }
+        NodeRef { idx, generation }
     }
 
     pub fn directed_edge(&mut self, from: NodeRef, to: NodeRef, e: E) -> EdgeRef {
-        let r = EdgeRef {
-            idx: self.edges.len(),
+        let data = EdgeData {
+            from,
+            to,
+            reverse: None,
+            value: e,
         };
-        self.edges.push(e);
+        let (idx, generation) = insert_entry(&mut self.edges, &mut self.free_edges, data);
+        let r = EdgeRef { idx, generation };
         self.adjacency[from.idx].push(AdjEntry { node: to, edge: r });
-        self.incidence.push(IncEntry { from, to });
         r
     }
+
+    /// Removes a node, along with every edge incident to it (both outgoing
+    /// and incoming). Returns `None` if `n` does not refer to a live node.
+    pub fn remove_node(&mut self, n: NodeRef) -> Option<N> {
+        get_entry(&self.nodes, n.idx, n.generation)?;
+
+        let outgoing: Vec<EdgeRef> = self.adjacency[n.idx].iter().map(|adj| adj.edge).collect();
+        let incoming: Vec<EdgeRef> = self
+            .edges
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, entry)| match &entry.slot {
+                Slot::Occupied(data) if data.to == n => Some(EdgeRef {
+                    idx,
+                    generation: entry.generation,
+                }),
+                _ => None,
+            })
+            .collect();
+
+        for edge in outgoing.into_iter().chain(incoming) {
+            self.remove_edge(edge);
+        }
+
+        self.adjacency[n.idx].clear();
+        remove_entry(&mut self.nodes, &mut self.free_nodes, n.idx, n.generation)
+    }
+
+    /// Removes an edge. Returns `None` if `e` does not refer to a live edge.
+    pub fn remove_edge(&mut self, e: EdgeRef) -> Option<E> {
+        let data = remove_entry(&mut self.edges, &mut self.free_edges, e.idx, e.generation)?;
+        self.adjacency[data.from.idx].retain(|adj| adj.edge != e);
+
+        if let Some(reverse) = data.reverse {
+            if let Some(partner) = get_entry_mut(&mut self.edges, reverse.idx, reverse.generation) {
+                partner.reverse = None;
+            }
+        }
+
+        Some(data.value)
+    }
+
+    pub fn get_node(&self, n: NodeRef) -> Option<&N> {
+        get_entry(&self.nodes, n.idx, n.generation)
+    }
+
+    pub fn get_node_mut(&mut self, n: NodeRef) -> Option<&mut N> {
+        get_entry_mut(&mut self.nodes, n.idx, n.generation)
+    }
+
+    pub fn get_edge(&self, e: EdgeRef) -> Option<&E> {
+        get_entry(&self.edges, e.idx, e.generation).map(|data| &data.value)
+    }
+
+    pub fn get_edge_mut(&mut self, e: EdgeRef) -> Option<&mut E> {
+        get_entry_mut(&mut self.edges, e.idx, e.generation).map(|data| &mut data.value)
+    }
+
+    /// The `(from, to)` nodes an edge was created with.
+    pub fn edge_endpoints(&self, e: EdgeRef) -> Option<(NodeRef, NodeRef)> {
+        get_entry(&self.edges, e.idx, e.generation).map(|data| (data.from, data.to))
+    }
 }
 
 impl<N, E: Clone> Graph<N, E> {
     pub fn undirected_edge(&mut self, a: NodeRef, b: NodeRef, e: E) -> [EdgeRef; 2] {
         let r1 = self.directed_edge(a, b, e.clone());
         let r2 = self.directed_edge(b, a, e);
+        get_entry_mut(&mut self.edges, r1.idx, r1.generation).unwrap().reverse = Some(r2);
+        get_entry_mut(&mut self.edges, r2.idx, r2.generation).unwrap().reverse = Some(r1);
         [r1, r2]
     }
 }
@@ -79,13 +233,13 @@ impl<N, E> Index<NodeRef> for Graph<N, E> {
     type Output = N;
 
     fn index(&self, index: NodeRef) -> &Self::Output {
-        &self.nodes[index.idx]
+        self.get_node(index).expect("stale or invalid NodeRef")
     }
 }
 
 impl<N, E> IndexMut<NodeRef> for Graph<N, E> {
     fn index_mut(&mut self, index: NodeRef) -> &mut Self::Output {
-        &mut self.nodes[index.idx]
+        self.get_node_mut(index).expect("stale or invalid NodeRef")
     }
 }
 
@@ -93,13 +247,13 @@ impl<N, E> Index<EdgeRef> for Graph<N, E> {
     type Output = E;
 
     fn index(&self, index: EdgeRef) -> &Self::Output {
-        &self.edges[index.idx]
+        self.get_edge(index).expect("stale or invalid EdgeRef")
     }
 }
 
 impl<N, E> IndexMut<EdgeRef> for Graph<N, E> {
     fn index_mut(&mut self, index: EdgeRef) -> &mut Self::Output {
-        &mut self.edges[index.idx]
+        self.get_edge_mut(index).expect("stale or invalid EdgeRef")
     }
 }
 
@@ -114,20 +268,20 @@ impl<N, E> Graph<N, E> {
         fn dfs<N, E>(
             graph: &Graph<N, E>,
             visited: &mut [bool],
-            node: usize,
+            node: NodeRef,
             node_visitor: &mut dyn FnMut(NodeRef, &N),
             edge_visitor: &mut dyn FnMut(EdgeRef, &E),
         ) {
-            if visited[node] {
+            if visited[node.idx] {
                 return;
             }
 
-            node_visitor(NodeRef { idx: node }, &graph.nodes[node]);
-            visited[node] = true;
+            node_visitor(node, &graph[node]);
+            visited[node.idx] = true;
 
-            for adj in &graph.adjacency[node] {
+            for adj in &graph.adjacency[node.idx] {
                 edge_visitor(adj.edge, &graph[adj.edge]);
-                dfs(graph, visited, adj.node.idx, node_visitor, edge_visitor);
+                dfs(graph, visited, adj.node, node_visitor, edge_visitor);
             }
         }
 
@@ -135,7 +289,7 @@ impl<N, E> Graph<N, E> {
         dfs(
             self,
             &mut visited,
-            begin.idx,
+            begin,
             &mut node_visitor,
             &mut edge_visitor,
         )
@@ -163,7 +317,7 @@ impl<N, E> Graph<N, E> {
         // inner function to keep monomorphized assembly small
         fn bfs<N, E>(
             graph: &Graph<N, E>,
-            begin: usize,
+            begin: NodeRef,
             node_visitor: &mut dyn FnMut(NodeRef, &N),
             edge_visitor: &mut dyn FnMut(EdgeRef, &E),
         ) {
@@ -172,21 +326,21 @@ impl<N, E> Graph<N, E> {
             queue.push_back(begin);
 
             while let Some(node) = queue.pop_front() {
-                if visited[node] {
+                if visited[node.idx] {
                     continue;
                 }
 
-                node_visitor(NodeRef { idx: node }, &graph.nodes[node]);
-                visited[node] = true;
+                node_visitor(node, &graph[node]);
+                visited[node.idx] = true;
 
-                for adj in &graph.adjacency[node] {
+                for adj in &graph.adjacency[node.idx] {
                     edge_visitor(adj.edge, &graph[adj.edge]);
-                    queue.push_back(adj.node.idx);
+                    queue.push_back(adj.node);
                 }
             }
         }
 
-        bfs(self, begin.idx, &mut node_visitor, &mut edge_visitor)
+        bfs(self, begin, &mut node_visitor, &mut edge_visitor)
     }
 
     /// Visit all the nodes in BFS order
@@ -200,12 +354,285 @@ impl<N, E> Graph<N, E> {
     }
 }
 
+impl<N, E> Graph<N, E> {
+    /// The edges leaving `node`, without their destination.
+    pub fn outgoing_edges(&self, node: NodeRef) -> impl Iterator<Item = EdgeRef> + '_ {
+        self.adjacency[node.idx].iter().map(|adj| adj.edge)
+    }
+
+    /// The edges leaving `node`, paired with the node they lead to.
+    pub fn neighbors(&self, node: NodeRef) -> impl Iterator<Item = (EdgeRef, NodeRef)> + '_ {
+        self.adjacency[node.idx].iter().map(|adj| (adj.edge, adj.node))
+    }
+
+    /// Lazily visits nodes in DFS order starting at `begin`, yielding each
+    /// reachable node exactly once. Unlike `visit_dfs`, this can be
+    /// composed with standard iterator adapters and stopped early.
+    pub fn dfs(&self, begin: NodeRef) -> Dfs<'_, N, E> {
+        Dfs {
+            graph: self,
+            visited: vec![false; self.nodes.len()],
+            stack: vec![begin],
+        }
+    }
+
+    /// Lazily visits nodes in BFS order starting at `begin`, yielding each
+    /// reachable node exactly once. Unlike `visit_bfs`, this can be
+    /// composed with standard iterator adapters and stopped early.
+    pub fn bfs(&self, begin: NodeRef) -> Bfs<'_, N, E> {
+        let mut queue = VecDeque::new();
+        queue.push_back(begin);
+        Bfs {
+            graph: self,
+            visited: vec![false; self.nodes.len()],
+            queue,
+        }
+    }
+}
+
+/// Lazy DFS traversal iterator returned by [`Graph::dfs`].
+pub struct Dfs<'g, N, E> {
+    graph: &'g Graph<N, E>,
+    visited: Vec<bool>,
+    stack: Vec<NodeRef>,
+}
+
+impl<N, E> Iterator for Dfs<'_, N, E> {
+    type Item = NodeRef;
+
+    fn next(&mut self) -> Option<NodeRef> {
+        while let Some(node) = self.stack.pop() {
+            if self.visited[node.idx] {
+                continue;
+            }
+            self.visited[node.idx] = true;
+
+            for adj in &self.graph.adjacency[node.idx] {
+                if !self.visited[adj.node.idx] {
+                    self.stack.push(adj.node);
+                }
+            }
+
+            return Some(node);
+        }
+        None
+    }
+}
+
+/// Lazy BFS traversal iterator returned by [`Graph::bfs`].
+pub struct Bfs<'g, N, E> {
+    graph: &'g Graph<N, E>,
+    visited: Vec<bool>,
+    queue: VecDeque<NodeRef>,
+}
+
+impl<N, E> Iterator for Bfs<'_, N, E> {
+    type Item = NodeRef;
+
+    fn next(&mut self) -> Option<NodeRef> {
+        while let Some(node) = self.queue.pop_front() {
+            if self.visited[node.idx] {
+                continue;
+            }
+            self.visited[node.idx] = true;
+
+            for adj in &self.graph.adjacency[node.idx] {
+                if !self.visited[adj.node.idx] {
+                    self.queue.push_back(adj.node);
+                }
+            }
+
+            return Some(node);
+        }
+        None
+    }
+}
+
+/// One direction of a residual-graph arc used by [`Graph::max_flow`] and
+/// [`Graph::min_cut`].
+struct FlowArc {
+    to: NodeRef,
+    cap: i32,
+    /// Index into the arc list of the arc going the other way.
+    pair: usize,
+}
+
+/// The result of [`Graph::min_cut`]: the min-cut value together with the set
+/// of nodes reachable from the source in the final residual graph. The cut
+/// edges are exactly the edges crossing from `source_side` to the rest of
+/// the graph.
+pub struct MinCut {
+    pub value: i32,
+    pub source_side: Vec<NodeRef>,
+}
+
 impl<N> Graph<N, i32> {
+    /// Builds the residual arc list for the flow network: each live edge
+    /// becomes a forward arc carrying its weight as capacity, paired with a
+    /// reverse arc. Edges added via `undirected_edge` are paired with each
+    /// other; edges added via `directed_edge` are paired with a freshly
+    /// created reverse arc of capacity 0.
+    fn residual_arcs(&self) -> (Vec<FlowArc>, Vec<Vec<usize>>) {
+        let mut arcs = Vec::with_capacity(self.edges.len() * 2);
+        let mut out = vec![Vec::new(); self.nodes.len()];
+        let mut paired = vec![false; self.edges.len()];
+
+        for i in 0..self.edges.len() {
+            if paired[i] {
+                continue;
+            }
+
+            let data = match &self.edges[i].slot {
+                Slot::Occupied(data) => data,
+                Slot::Free { .. } => continue,
+            };
+
+            let fwd = arcs.len();
+            arcs.push(FlowArc {
+                to: data.to,
+                cap: data.value,
+                pair: fwd + 1,
+            });
+            out[data.from.idx].push(fwd);
+
+            let bwd_cap = match data.reverse {
+                Some(r) => {
+                    paired[r.idx] = true;
+                    get_entry(&self.edges, r.idx, r.generation).map_or(0, |rd| rd.value)
+                }
+                None => 0,
+            };
+            let bwd = arcs.len();
+            arcs.push(FlowArc {
+                to: data.from,
+                cap: bwd_cap,
+                pair: fwd,
+            });
+            out[data.to.idx].push(bwd);
+        }
+
+        (arcs, out)
+    }
+
+    /// Assigns each node its distance from `from` along arcs with spare
+    /// capacity. Returns `None` if `to` is unreachable.
+    fn levels(arcs: &[FlowArc], out: &[Vec<usize>], from: NodeRef, to: NodeRef) -> Option<Vec<i32>> {
+        let mut level = vec![-1; out.len()];
+        level[from.idx] = 0;
+
+        let mut queue = VecDeque::new();
+        queue.push_back(from.idx);
+        while let Some(node) = queue.pop_front() {
+            for &arc in &out[node] {
+                let arc = &arcs[arc];
+                if arc.cap > 0 && level[arc.to.idx] < 0 {
+                    level[arc.to.idx] = level[node] + 1;
+                    queue.push_back(arc.to.idx);
+                }
+            }
+        }
+
+        if level[to.idx] < 0 {
+            None
+        } else {
+            Some(level)
+        }
+    }
+
+    /// Pushes a single blocking-flow path from `node` to `to`, advancing
+    /// `frontier` (the per-node iterator into `out`) past arcs that turn out
+    /// to be dead ends so later calls don't revisit them.
+    fn blocking_flow(
+        arcs: &mut [FlowArc],
+        out: &[Vec<usize>],
+        level: &[i32],
+        frontier: &mut [usize],
+        node: usize,
+        to: usize,
+        pushed: i32,
+    ) -> i32 {
+        if node == to {
+            return pushed;
+        }
+
+        while frontier[node] < out[node].len() {
+            let arc = out[node][frontier[node]];
+            let (cap, next, pair) = (arcs[arc].cap, arcs[arc].to.idx, arcs[arc].pair);
+
+            if cap > 0 && level[next] == level[node] + 1 {
+                let sent = Self::blocking_flow(arcs, out, level, frontier, next, to, pushed.min(cap));
+                if sent > 0 {
+                    arcs[arc].cap -= sent;
+                    arcs[pair].cap += sent;
+                    return sent;
+                }
+            }
+
+            frontier[node] += 1;
+        }
+
+        0
+    }
+
+    /// Runs Dinic's algorithm, returning the saturated residual graph
+    /// alongside the total flow pushed from `from` to `to`.
+    fn dinic(&self, from: NodeRef, to: NodeRef) -> (Vec<FlowArc>, Vec<Vec<usize>>, i32) {
+        let (mut arcs, out) = self.residual_arcs();
+        let mut flow = 0;
+
+        while let Some(level) = Self::levels(&arcs, &out, from, to) {
+            let mut frontier = vec![0; out.len()];
+            loop {
+                let pushed =
+                    Self::blocking_flow(&mut arcs, &out, &level, &mut frontier, from.idx, to.idx, i32::MAX);
+                if pushed == 0 {
+                    break;
+                }
+                flow += pushed;
+            }
+        }
+
+        (arcs, out, flow)
+    }
+
+    /// Computes the maximum flow from `from` to `to` using Dinic's
+    /// algorithm, treating each edge's weight as its capacity.
     pub fn max_flow(&self, from: NodeRef, to: NodeRef) -> i32 {
-        let mut i;
-        let mut flow_on_path;
+        self.dinic(from, to).2
     }
-    pub fn min_cut(&self, from: NodeRef, to: NodeRef) -> i32 {
-        self.max_flow(from, to)
+
+    /// Computes a minimum cut separating `from` from `to`. By the max-flow
+    /// min-cut theorem its value equals `max_flow(from, to)`; `source_side`
+    /// lists the nodes still reachable from `from` in the saturated
+    /// residual graph, so the cut edges are those leaving that set.
+    pub fn min_cut(&self, from: NodeRef, to: NodeRef) -> MinCut {
+        let (arcs, out, value) = self.dinic(from, to);
+
+        let mut visited = vec![false; out.len()];
+        visited[from.idx] = true;
+        let mut queue = VecDeque::new();
+        queue.push_back(from.idx);
+        while let Some(node) = queue.pop_front() {
+            for &arc in &out[node] {
+                let arc = &arcs[arc];
+                if arc.cap > 0 && !visited[arc.to.idx] {
+                    visited[arc.to.idx] = true;
+                    queue.push_back(arc.to.idx);
+                }
+            }
+        }
+
+        let source_side = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(idx, entry)| visited[*idx] && matches!(entry.slot, Slot::Occupied(_)))
+            .map(|(idx, entry)| NodeRef {
+                idx,
+                generation: entry.generation,
+            })
+            .collect();
+
+        MinCut { value, source_side }
     }
 }